@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+/// How long a period transition takes to settle.
+pub static TRANSITION_DURATION: Duration = Duration::from_millis(300);
+
+/// A value clamped to `0.0..=1.0`, representing how far through an
+/// animation we are.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Percentage(f64);
+
+impl Percentage {
+    pub fn new(value: f64) -> Self {
+        Self(value.clamp(0.0, 1.0))
+    }
+
+    pub fn get(self) -> f64 {
+        self.0
+    }
+}
+
+/// Slow start, fast middle, slow end.
+pub fn ease_in_out_cubic(p: Percentage) -> f64 {
+    let p = p.get();
+    if p < 0.5 {
+        4. * p * p * p
+    } else {
+        1. - (-2. * p + 2.).powi(3) / 2.
+    }
+}
+
+/// Linearly blends two RGB colors and renders the result as a CSS `rgb()`.
+pub fn interpolate_color(from: (u8, u8, u8), to: (u8, u8, u8), t: f64) -> String {
+    let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    format!("rgb({}, {}, {})", lerp(from.0, to.0), lerp(from.1, to.1), lerp(from.2, to.2))
+}
@@ -1,4 +1,4 @@
-use gloo::{console, timers::callback::Interval};
+use gloo::{console, events::EventListener, render::{request_animation_frame, AnimationFrame}, timers::callback::{Interval, Timeout}};
 use std::borrow::Borrow;
 use std::convert::Into;
 use std::time::Duration;
@@ -7,12 +7,99 @@ use web_sys::{Notification, NotificationOptions, InputEvent, HtmlInputElement, H
 use yew::{html, Component, Context, Html};
 use serde::{Serialize, Deserialize};
 
+mod animation;
+use animation::{ease_in_out_cubic, interpolate_color, Percentage, TRANSITION_DURATION};
+
+mod sync;
+use sync::Channel;
+
+static HEARTBEAT_CHECK_INTERVAL: u32 = 1000;
+static HEARTBEAT_TIMEOUT_MILLIS: f64 = 3000.;
+
 static TICK_INTERVAL: u32 = 1000;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum PeriodKind {
+    Focus,
+    ShortBreak,
+    LongBreak,
+}
+
+impl PeriodKind {
+    /// The progress bar's base color for this kind of period, blended during
+    /// `PeriodTransition`s by `App::current_fill_color`.
+    fn color(self) -> (u8, u8, u8) {
+        match self {
+            PeriodKind::Focus => (217, 72, 15),
+            PeriodKind::ShortBreak => (47, 158, 68),
+            PeriodKind::LongBreak => (25, 113, 194),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 struct Period {
     name: String,
     duration: Duration,
+    kind: PeriodKind,
+}
+
+/// A point-in-time copy of the editable state, so `Msg::Undo`/`Msg::Redo`
+/// can restore it.
+#[derive(Clone)]
+struct Snapshot {
+    periods: Vec<Period>,
+    current_period: usize,
+    completed_focus: u32,
+}
+
+/// What the leader tab broadcasts to its followers over `BroadcastChannel`
+/// on every tick.
+#[derive(Serialize, Deserialize)]
+struct SyncState {
+    leader_id: u64,
+    current_period: usize,
+    progress_millis: u64,
+    running: bool,
+}
+
+/// The live session state needed to resume a running countdown across a
+/// page reload, mirroring what `interval`/`current_period` track at runtime.
+#[derive(Serialize, Deserialize)]
+struct Session {
+    current_period: usize,
+    running: bool,
+    tick_start: f64,
+}
+
+/// Settings for automatic Pomodoro cycling, persisted alongside `periods`.
+#[derive(Clone, Serialize, Deserialize)]
+struct CycleConfig {
+    focus_before_long_break: u32,
+    auto_advance: bool,
+}
+
+impl Default for CycleConfig {
+    fn default() -> Self {
+        Self {
+            focus_before_long_break: 4,
+            auto_advance: false,
+        }
+    }
+}
+
+/// What's persisted under the `pomyu_periods` key: the period list plus the
+/// auto-cycling settings that apply to it.
+#[derive(Serialize)]
+struct PeriodsDataRef<'a> {
+    periods: &'a [Period],
+    cycle_config: &'a CycleConfig,
+}
+
+#[derive(Deserialize)]
+struct PeriodsData {
+    periods: Vec<Period>,
+    cycle_config: CycleConfig,
 }
 
 #[derive(Clone)]
@@ -23,9 +110,28 @@ pub enum Msg {
     Finish,
     Resume,
     Tick(u32),
+    AnimationTick,
+    VisibilityChange,
     UpdateName(usize, String),
     UpdateMinutes(usize, u64),
     UpdateSeconds(usize, u64),
+    UpdateDuration(usize, String),
+    ToggleAutoAdvance,
+    UpdateFocusBeforeLongBreak(u32),
+    Undo,
+    Redo,
+    SyncReceived(String),
+    HeartbeatCheck,
+    CatchUpNotify,
+}
+
+/// Tracks the short animated blend from the end of one period to the start
+/// of the next, so the progress bar doesn't just snap back to empty.
+struct PeriodTransition {
+    started_at: f64,
+    from_fraction: f64,
+    to_fraction: f64,
+    from_color: (u8, u8, u8),
 }
 
 impl Msg {
@@ -44,9 +150,27 @@ impl Msg {
 pub struct App {
     messages: Vec<&'static str>,
     interval: Option<(Interval, u64, f64)>,
+    raf: Option<AnimationFrame>,
     progress: Option<Duration>,
     periods: Vec<Period>,
     current_period: usize,
+    period_transition: Option<PeriodTransition>,
+    cycle_config: CycleConfig,
+    completed_focus: u32,
+    undo_stack: Vec<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+    // Kept alive for the lifetime of the component; dropping it unregisters the listener.
+    _visibility_listener: Option<EventListener>,
+
+    // Cross-tab sync: the leader owns the Interval/wall clock and broadcasts
+    // it; followers just mirror whatever the leader last reported.
+    tab_id: u64,
+    is_leader: bool,
+    last_remote_seen: f64,
+    remote_running: bool,
+    sync_channel: Option<Channel>,
+    _heartbeat_check: Option<Interval>,
+    _catch_up_timeout: Option<Timeout>,
 }
 
 impl App {
@@ -57,9 +181,142 @@ impl App {
             .unwrap_or(Duration::from_secs(25 * 60))
     }
 
+    /// Where to go after the current period finishes: normally the next
+    /// period in the list, but after `cycle_config.focus_before_long_break`
+    /// completed focus periods, jump ahead to the first `LongBreak` instead.
+    fn next_period_index(&self) -> usize {
+        let len = usize::max(1, self.periods.len());
+        let sequential_next = (self.current_period + 1) % len;
+        let just_finished_focus = self
+            .periods
+            .get(self.current_period)
+            .map(|period| period.kind == PeriodKind::Focus)
+            .unwrap_or(false);
+        if just_finished_focus
+            && self.completed_focus > 0
+            && self.completed_focus % self.cycle_config.focus_before_long_break == 0
+        {
+            if let Some(index) = self.periods.iter().position(|period| period.kind == PeriodKind::LongBreak) {
+                return index;
+            }
+        }
+        sequential_next
+    }
+
     fn reset(&mut self) {
         self.interval = None;
+        self.raf = None;
         self.progress = None;
+        self.period_transition = None;
+    }
+
+    /// If a timer is live when `Undo`/`Redo` restores a snapshot whose
+    /// `current_period` differs from the one it was counting down, the
+    /// interval/progress are left tracking the wrong period. Stop the timer
+    /// so the restored state is the one the user sees, rather than a stale
+    /// countdown silently running against the old period.
+    fn reset_running_timer(&mut self) {
+        if self.interval.is_some() || self.progress.is_some() {
+            self.reset();
+            log_error(self.save_session(), "Could not save session");
+        }
+    }
+
+    /// Broadcasts the authoritative running state to any other open tabs.
+    /// Only meaningful while this tab is the leader.
+    fn broadcast_state(&self) {
+        if let Some(channel) = &self.sync_channel {
+            let state = SyncState {
+                leader_id: self.tab_id,
+                current_period: self.current_period,
+                progress_millis: self.progress.map(|p| p.as_millis() as u64).unwrap_or(0),
+                running: self.interval.is_some(),
+            };
+            if let Ok(payload) = serde_json::to_string(&state) {
+                channel.send(&payload);
+            }
+        }
+    }
+
+    /// A lower-id tab has announced itself as leader; step aside and stop
+    /// running our own Interval/rAF so we don't double-notify.
+    fn demote_to_follower(&mut self) {
+        self.is_leader = false;
+        self.reset();
+    }
+
+    /// No State broadcast has arrived within the heartbeat timeout (the
+    /// leader tab likely closed); take over from the last state we saw.
+    fn promote_to_leader(&mut self, ctx: &Context<Self>) {
+        self.is_leader = true;
+        if self.remote_running {
+            if let Some(progress) = self.progress {
+                let handle = {
+                    let link = ctx.link().clone();
+                    Interval::new(TICK_INTERVAL, move || link.send_message(Msg::Tick(TICK_INTERVAL)))
+                };
+                let tick_start = get_utc_millis() - progress.as_millis() as f64;
+                self.interval = Some((handle, 0, tick_start));
+                self.schedule_animation_frame(ctx);
+            }
+        }
+    }
+
+    /// Records the current periods/current_period so it can be restored by
+    /// `Msg::Undo`. Any pending redo is discarded, matching typical undo/redo
+    /// semantics once a fresh edit is made.
+    fn push_undo_snapshot(&mut self) {
+        self.undo_stack.push(Snapshot {
+            periods: self.periods.clone(),
+            current_period: self.current_period,
+            completed_focus: self.completed_focus,
+        });
+        self.redo_stack.clear();
+    }
+
+    fn schedule_animation_frame(&mut self, ctx: &Context<Self>) {
+        let link = ctx.link().clone();
+        self.raf = Some(request_animation_frame(move |_| {
+            link.send_message(Msg::AnimationTick);
+        }));
+    }
+
+    /// The fraction (0.0..=1.0) of the current period's bar that should be
+    /// filled right now, blending in any in-flight period transition.
+    fn current_fill_fraction(&self) -> f64 {
+        let target = self
+            .progress
+            .map(|p| p.as_secs_f64() / self.get_current_period_length().as_secs_f64())
+            .unwrap_or(0.0)
+            .clamp(0.0, 1.0);
+        if let Some(transition) = &self.period_transition {
+            let elapsed = get_utc_millis() - transition.started_at;
+            let t = Percentage::new(elapsed / TRANSITION_DURATION.as_millis() as f64);
+            if t.get() < 1.0 {
+                let eased = ease_in_out_cubic(t);
+                return transition.from_fraction + (transition.to_fraction - transition.from_fraction) * eased;
+            }
+        }
+        target
+    }
+
+    /// The progress bar's color right now, blending from the previous
+    /// period's color to the current one's over any in-flight transition.
+    fn current_fill_color(&self) -> String {
+        let current_color = self
+            .periods
+            .get(self.current_period)
+            .map(|p| p.kind.color())
+            .unwrap_or(PeriodKind::Focus.color());
+        if let Some(transition) = &self.period_transition {
+            let elapsed = get_utc_millis() - transition.started_at;
+            let t = Percentage::new(elapsed / TRANSITION_DURATION.as_millis() as f64);
+            if t.get() < 1.0 {
+                let eased = ease_in_out_cubic(t);
+                return interpolate_color(transition.from_color, current_color, eased);
+            }
+        }
+        interpolate_color(current_color, current_color, 1.)
     }
 
     fn notify(&mut self, milliseconds_update: u32) {
@@ -76,8 +333,13 @@ impl App {
                 console::log!("notification_periods_since_over", notification_periods_since_over);
                 let notification_periods_before = -minutes_left_before / notification_period_len;
                 console::log!("notification_periods_before", notification_periods_before);
-                if notification_periods_since_over.floor() > notification_periods_before.floor() {
-                    // We should notify
+                let boundaries_crossed = notification_periods_since_over.floor() - notification_periods_before.floor();
+                if boundaries_crossed > 0. {
+                    // One or more 5-minute boundaries were crossed since the last check (maybe
+                    // several at once, if the tab was backgrounded and throttled, or this is a
+                    // resync after a missed interval). Emit a single notification reporting the
+                    // true total overdue time rather than one per boundary.
+                    console::log!("boundaries_crossed", boundaries_crossed);
                     let period_name =
                         if let Some(period) = self.periods.get(self.current_period) {
                             period.name.clone()
@@ -107,7 +369,11 @@ impl App {
     fn save_periods(&self) -> Result<(), JsValue> {
         let window = window().ok_or("no window")?;
         let storage = window.local_storage()?.ok_or("no local storage")?;
-        let content = serde_json::to_string(&self.periods).map_err(|_| "Error serializing periods")?;
+        let data = PeriodsDataRef {
+            periods: &self.periods,
+            cycle_config: &self.cycle_config,
+        };
+        let content = serde_json::to_string(&data).map_err(|_| "Error serializing periods")?;
         storage.set("pomyu_periods", &content)?;
         Ok(())
     }
@@ -116,9 +382,58 @@ impl App {
         let window = window().ok_or("no window")?;
         let storage = window.local_storage()?.ok_or("no local storage")?;
         let content = storage.get("pomyu_periods")?.ok_or("pomyu_periods not found")?;
-        self.periods = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        let data: PeriodsData = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        self.periods = data.periods;
+        self.cycle_config = data.cycle_config;
         Ok(())
     }
+
+    fn save_session(&self) -> Result<(), JsValue> {
+        let window = window().ok_or("no window")?;
+        let storage = window.local_storage()?.ok_or("no local storage")?;
+        let session = Session {
+            current_period: self.current_period,
+            running: self.interval.is_some(),
+            tick_start: self.interval.as_ref().map(|(_, _, tick_start)| *tick_start).unwrap_or(0.),
+        };
+        let content = serde_json::to_string(&session).map_err(|_| "Error serializing session")?;
+        storage.set("pomyu_session", &content)?;
+        Ok(())
+    }
+
+    fn load_session(&self) -> Result<Session, JsValue> {
+        let window = window().ok_or("no window")?;
+        let storage = window.local_storage()?.ok_or("no local storage")?;
+        let content = storage.get("pomyu_session")?.ok_or("pomyu_session not found")?;
+        serde_json::from_str(&content).map_err(|e| e.to_string().into())
+    }
+
+    /// Reconstructs a running timer from a persisted `Session`, then schedules
+    /// a catch-up notification pass (`Msg::CatchUpNotify`) for anything that
+    /// finished while the page was closed.
+    fn resume_session(&mut self, ctx: &Context<Self>, session: Session) {
+        self.current_period = session.current_period;
+        let handle = {
+            let link = ctx.link().clone();
+            Interval::new(TICK_INTERVAL, move || link.send_message(Msg::Tick(TICK_INTERVAL)))
+        };
+        self.interval = Some((handle, 0, session.tick_start));
+        // Seed from the period's start rather than resyncing to the wall clock
+        // now: the deferred catch-up `notify()` needs a stale "before" value
+        // to diff against, or every boundary crossed while the tab was closed
+        // goes undetected. Don't schedule the animation frame yet either --
+        // its rAF loop would resync `progress` to "now" within a frame or two,
+        // erasing that staleness before the deferred notify gets to see it.
+        self.progress = Some(Duration::ZERO);
+        // Don't notify yet: if another tab already has this session open, it's
+        // running the same catch-up pass, and leader election hasn't resolved
+        // this early (it needs at least one Tick/broadcast round-trip). Wait
+        // for that to settle, then let only the tab that's still leader notify.
+        self._catch_up_timeout = Some({
+            let link = ctx.link().clone();
+            Timeout::new(TICK_INTERVAL, move || link.send_message(Msg::CatchUpNotify))
+        });
+    }
 }
 
 fn log_error<V, E: Into<JsValue>, S: Borrow<str>>(result: Result<V, E>, err_msg: S) -> Option<V> {
@@ -141,6 +456,81 @@ fn format_duration(duration: Duration) -> String {
     )
 }
 
+/// Renders a `Duration` back into the `<number><unit>` shorthand understood
+/// by `parse_duration_human`, e.g. `5400s` -> `"1h30m"`.
+fn format_duration_human(duration: Duration) -> String {
+    let total_secs = duration.as_secs();
+    let hours = total_secs / 3600;
+    let minutes = (total_secs % 3600) / 60;
+    let seconds = total_secs % 60;
+    let mut out = String::new();
+    if hours > 0 {
+        out += &format!("{}h", hours);
+    }
+    if minutes > 0 {
+        out += &format!("{}m", minutes);
+    }
+    if seconds > 0 || out.is_empty() {
+        out += &format!("{}s", seconds);
+    }
+    out
+}
+
+/// Parses a human-friendly duration string made of `<number><unit>` tokens
+/// (unit is `h`, `m`, or `s`, e.g. `"1h30m"`, `"90s"`, `"25 m"`), summing
+/// the components. Returns an error describing the problem on malformed
+/// or empty input, leaving the caller's existing duration untouched.
+fn parse_duration_human(input: &str) -> Result<Duration, String> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err("duration is empty".to_string());
+    }
+    let mut chars = trimmed.chars().peekable();
+    let mut total = Duration::ZERO;
+    let mut found_any = false;
+    while chars.peek().is_some() {
+        while chars.peek() == Some(&' ') {
+            chars.next();
+        }
+        if chars.peek().is_none() {
+            break;
+        }
+        let mut number = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_ascii_digit() || c == '.' {
+                number.push(c);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        if number.is_empty() {
+            return Err(format!("expected a number in duration '{}'", input));
+        }
+        while chars.peek() == Some(&' ') {
+            chars.next();
+        }
+        let unit = chars
+            .next()
+            .ok_or_else(|| format!("expected a unit (h/m/s) after '{}' in '{}'", number, input))?;
+        let value: f64 = number
+            .parse()
+            .map_err(|_| format!("invalid number '{}' in duration '{}'", number, input))?;
+        let seconds = match unit {
+            'h' => value * 3600.,
+            'm' => value * 60.,
+            's' => value,
+            other => return Err(format!("unknown duration unit '{}' in '{}'", other, input)),
+        };
+        total += Duration::from_secs_f64(seconds);
+        found_any = true;
+    }
+    if !found_any {
+        return Err(format!("could not parse duration '{}'", input));
+    }
+    Ok(total)
+}
+
 fn get_utc_millis() -> f64 {
     js_sys::Date::new_0().get_time()
 }
@@ -160,32 +550,74 @@ impl Component for App {
     type Message = Msg;
     type Properties = ();
 
-    fn create(_ctx: &Context<Self>) -> Self {
+    fn create(ctx: &Context<Self>) -> Self {
         let mut this = Self {
             messages: Vec::new(),
             interval: None,
+            raf: None,
             progress: None,
+            period_transition: None,
             periods: vec![
                 Period {
                     name: "Focus".to_string(),
                     duration: Duration::from_secs(25 * 60),
+                    kind: PeriodKind::Focus,
                 },
                 Period {
                     name: "Small break".to_string(),
                     duration: Duration::from_secs(5 * 60),
+                    kind: PeriodKind::ShortBreak,
                 },
                 Period {
                     name: "Focus".to_string(),
                     duration: Duration::from_secs(25 * 60),
+                    kind: PeriodKind::Focus,
                 },
                 Period {
                     name: "Full break".to_string(),
                     duration: Duration::from_secs(15 * 60),
+                    kind: PeriodKind::LongBreak,
                 },
             ],
             current_period: 0,
+            cycle_config: CycleConfig::default(),
+            completed_focus: 0,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            _visibility_listener: None,
+            tab_id: (get_utc_millis() as u64)
+                .wrapping_mul(1_000_003)
+                .wrapping_add((js_sys::Math::random() * 1_000_000.) as u64),
+            is_leader: true,
+            last_remote_seen: get_utc_millis(),
+            remote_running: false,
+            sync_channel: None,
+            _heartbeat_check: None,
+            _catch_up_timeout: None,
         };
         log_error(this.load_periods(), "Could not load periods");
+        if let Ok(session) = this.load_session() {
+            if session.running {
+                this.resume_session(ctx, session);
+            }
+        }
+        this._visibility_listener = window().and_then(|w| w.document()).map(|document| {
+            let link = ctx.link().clone();
+            EventListener::new(&document, "visibilitychange", move |_| {
+                link.send_message(Msg::VisibilityChange);
+            })
+        });
+        this.sync_channel = {
+            let link = ctx.link().clone();
+            log_error(
+                Channel::new("pomyu_sync", move |payload| link.send_message(Msg::SyncReceived(payload))),
+                "Could not open sync channel",
+            )
+        };
+        this._heartbeat_check = Some({
+            let link = ctx.link().clone();
+            Interval::new(HEARTBEAT_CHECK_INTERVAL, move || link.send_message(Msg::HeartbeatCheck))
+        });
         this
     }
 
@@ -214,35 +646,112 @@ impl Component for App {
                     })
                 };
                 self.interval = Some((handle, 0, get_utc_millis()));
+                self.schedule_animation_frame(ctx);
 
                 self.messages.push("resume");
+                log_error(self.save_session(), "Could not save session");
                 true
             }
             Msg::Reset => {
                 self.reset();
                 self.messages.push("reset");
+                log_error(self.save_session(), "Could not save session");
                 true
             }
             Msg::Pause => {
                 self.interval = None;
+                self.raf = None;
                 self.messages.push("pause");
+                log_error(self.save_session(), "Could not save session");
                 true
             }
             Msg::Finish => {
+                self.push_undo_snapshot();
+                let from_fraction = self.current_fill_fraction();
+                let from_color = self
+                    .periods
+                    .get(self.current_period)
+                    .map(|p| p.kind.color())
+                    .unwrap_or(PeriodKind::Focus.color());
+                if self.periods.get(self.current_period).map(|p| p.kind) == Some(PeriodKind::Focus) {
+                    self.completed_focus += 1;
+                }
+                self.current_period = self.next_period_index();
                 self.reset();
                 self.messages.push("Finish!");
-                self.interval = None;
-                self.progress = None;
-                self.current_period += 1;
-                self.current_period %= usize::max(1, self.periods.len());
+                self.period_transition = Some(PeriodTransition {
+                    started_at: get_utc_millis(),
+                    from_fraction,
+                    to_fraction: 0.,
+                    from_color,
+                });
+                // `reset()` just cleared `raf`; the transition still needs to be
+                // driven to completion even when nothing else is ticking (e.g. a
+                // manual Skip with auto-advance off).
+                self.schedule_animation_frame(ctx);
+                log_error(self.save_session(), "Could not save session");
+                if self.cycle_config.auto_advance {
+                    let next_name = self
+                        .periods
+                        .get(self.current_period)
+                        .map(|p| p.name.clone())
+                        .unwrap_or_else(|| "Next period".to_string());
+                    let mut note_options = NotificationOptions::new();
+                    note_options.body(&format!("{} started", next_name));
+                    log_error(
+                        Notification::new_with_options("Pomyu", &note_options),
+                        "Could not show notification",
+                    );
+                    self.update(ctx, Msg::Start);
+                }
                 true
             }
             Msg::Tick(milliseconds) => {
-                self.notify(milliseconds);
+                if self.is_leader {
+                    self.notify(milliseconds);
+                    self.broadcast_state();
+                }
+                true
+            }
+            Msg::AnimationTick => {
+                if let Some((_, _, tick_start)) = &self.interval {
+                    let new_progress = Duration::from_millis((get_utc_millis() - tick_start) as u64);
+                    // Never let the repaint rewind progress a tick/resume already advanced past.
+                    self.progress = Some(self.progress.map_or(new_progress, |p| Duration::max(new_progress, p)));
+                }
+                if let Some(transition) = &self.period_transition {
+                    if get_utc_millis() - transition.started_at >= TRANSITION_DURATION.as_millis() as f64 {
+                        self.period_transition = None;
+                    }
+                }
+                if self.is_leader
+                    && self.cycle_config.auto_advance
+                    && self.progress.map_or(false, |p| p >= self.get_current_period_length())
+                {
+                    // The period ran out while auto-advance is on; don't wait for the
+                    // user to notice the relabeled button, finish it ourselves.
+                    return self.update(ctx, Msg::Finish);
+                }
+                if self.interval.is_some() || self.period_transition.is_some() {
+                    self.schedule_animation_frame(ctx);
+                }
+                true
+            }
+            Msg::VisibilityChange => {
+                let became_visible = window()
+                    .and_then(|w| w.document())
+                    .map(|document| !document.hidden())
+                    .unwrap_or(false);
+                if became_visible && self.interval.is_some() {
+                    // Resync to the wall clock and run the catch-up notification pass
+                    // immediately, rather than waiting for the next throttled tick.
+                    self.notify(0);
+                }
                 true
             }
 
             Msg::UpdateName(period_number, new_name) => {
+                self.push_undo_snapshot();
                 if let Some(period) = self.periods.get_mut(period_number) {
                     period.name = new_name;
                 }
@@ -251,6 +760,7 @@ impl Component for App {
             }
 
             Msg::UpdateMinutes(period_number, minutes) => {
+                self.push_undo_snapshot();
                 if let Some(period) = self.periods.get_mut(period_number) {
                     // Subtract existing minutes
                     period.duration -= Duration::from_secs(60 * (period.duration.as_secs() / 60));
@@ -262,6 +772,7 @@ impl Component for App {
             }
 
             Msg::UpdateSeconds(period_number, seconds) => {
+                self.push_undo_snapshot();
                 if let Some(period) = self.periods.get_mut(period_number) {
                     // Round down to nearest minute
                     period.duration = Duration::from_secs(60 * (period.duration.as_secs() / 60));
@@ -270,6 +781,111 @@ impl Component for App {
                 log_error(self.save_periods(), "Could not save periods");
                 true
             }
+
+            Msg::UpdateDuration(period_number, text) => {
+                match parse_duration_human(&text) {
+                    Ok(duration) => {
+                        self.push_undo_snapshot();
+                        if let Some(period) = self.periods.get_mut(period_number) {
+                            period.duration = duration;
+                        }
+                        log_error(self.save_periods(), "Could not save periods");
+                    }
+                    Err(e) => {
+                        log_error(Err::<(), _>(e), "Could not parse duration");
+                    }
+                }
+                true
+            }
+
+            Msg::ToggleAutoAdvance => {
+                self.cycle_config.auto_advance = !self.cycle_config.auto_advance;
+                log_error(self.save_periods(), "Could not save periods");
+                true
+            }
+
+            Msg::UpdateFocusBeforeLongBreak(count) => {
+                self.cycle_config.focus_before_long_break = count.max(1);
+                log_error(self.save_periods(), "Could not save periods");
+                true
+            }
+
+            Msg::Undo => {
+                if let Some(snapshot) = self.undo_stack.pop() {
+                    self.redo_stack.push(Snapshot {
+                        periods: self.periods.clone(),
+                        current_period: self.current_period,
+                        completed_focus: self.completed_focus,
+                    });
+                    let period_changed = snapshot.current_period != self.current_period;
+                    self.periods = snapshot.periods;
+                    self.current_period = snapshot.current_period;
+                    self.completed_focus = snapshot.completed_focus;
+                    if period_changed {
+                        self.reset_running_timer();
+                    }
+                    log_error(self.save_periods(), "Could not save periods");
+                }
+                true
+            }
+
+            Msg::Redo => {
+                if let Some(snapshot) = self.redo_stack.pop() {
+                    self.undo_stack.push(Snapshot {
+                        periods: self.periods.clone(),
+                        current_period: self.current_period,
+                        completed_focus: self.completed_focus,
+                    });
+                    let period_changed = snapshot.current_period != self.current_period;
+                    self.periods = snapshot.periods;
+                    self.current_period = snapshot.current_period;
+                    self.completed_focus = snapshot.completed_focus;
+                    if period_changed {
+                        self.reset_running_timer();
+                    }
+                    log_error(self.save_periods(), "Could not save periods");
+                }
+                true
+            }
+
+            Msg::SyncReceived(payload) => {
+                if let Ok(state) = serde_json::from_str::<SyncState>(&payload) {
+                    self.last_remote_seen = get_utc_millis();
+                    // Lower tab_id always wins a leadership collision.
+                    if self.is_leader && state.leader_id < self.tab_id {
+                        self.demote_to_follower();
+                    }
+                    if !self.is_leader {
+                        self.current_period = state.current_period;
+                        self.progress = Some(Duration::from_millis(state.progress_millis));
+                        self.remote_running = state.running;
+                    }
+                }
+                true
+            }
+
+            Msg::HeartbeatCheck => {
+                if !self.is_leader && get_utc_millis() - self.last_remote_seen > HEARTBEAT_TIMEOUT_MILLIS {
+                    self.promote_to_leader(ctx);
+                    true
+                } else {
+                    false
+                }
+            }
+
+            Msg::CatchUpNotify => {
+                if self.is_leader && self.interval.is_some() {
+                    self.notify(0);
+                }
+                // `resume_session` deliberately left the animation frame loop
+                // unscheduled so this catch-up pass could see a stale
+                // `progress`; now that it's run (or there's nothing left to
+                // catch up on), start animating if there's still something to.
+                if self.progress.is_some() {
+                    self.schedule_animation_frame(ctx);
+                }
+                true
+            }
         }
     }
 
@@ -304,25 +920,70 @@ impl Component for App {
                     }
                 </div>
                 <progress
+                    class={
+                        if self.period_transition.is_some() {
+                            "period-transitioning"
+                        } else {
+                            ""
+                        }
+                    }
+                    style={ format!("accent-color: {};", self.current_fill_color()) }
                     value={
-                        self.progress.map(|p| p.as_millis()).unwrap_or(0).to_string()
+                        ((self.current_fill_fraction() * self.get_current_period_length().as_millis() as f64) as u64).to_string()
                     }
                     max={ self.get_current_period_length().as_millis().to_string() }>
                 </progress>
+                {
+                    if !self.is_leader {
+                        html! { <div class="sync-notice">{ "Following the timer running in another tab" }</div> }
+                    } else {
+                        html! {}
+                    }
+                }
                 <div class="grid">
-                    <button disabled={self.progress.is_none()}
+                    <button disabled={self.progress.is_none() || !self.is_leader}
                             onclick={ctx.link().callback(|_| Msg::Reset)}>
                         { "Reset" }
                     </button>
-                    <button onclick={ctx.link().callback( move |_| center_button_msg.clone()) }>
+                    <button disabled={self.undo_stack.is_empty()}
+                            onclick={ctx.link().callback(|_| Msg::Undo)}>
+                        { "Undo" }
+                    </button>
+                    <button disabled={self.redo_stack.is_empty()}
+                            onclick={ctx.link().callback(|_| Msg::Redo)}>
+                        { "Redo" }
+                    </button>
+                    <button disabled={!self.is_leader}
+                            onclick={ctx.link().callback( move |_| center_button_msg.clone()) }>
                         {
                             center_button_contents
                         }
                     </button>
-                    <button onclick={ctx.link().callback(|_| Msg::Finish)}>
+                    <button disabled={!self.is_leader}
+                            onclick={ctx.link().callback(|_| Msg::Finish)}>
                         { "Skip" }
                     </button>
                 </div>
+                <div class="grid cycle-config">
+                    <label>
+                        <input type="checkbox"
+                            checked={self.cycle_config.auto_advance}
+                            onclick={ctx.link().callback(|_| Msg::ToggleAutoAdvance)}/>
+                        { "Auto-advance" }
+                    </label>
+                    <label>
+                        { "Focus periods before long break" }
+                        <input type="number" min=1
+                            disabled={!self.cycle_config.auto_advance}
+                            oninput={ctx.link().batch_callback(|e: InputEvent| {
+                                e.target()
+                                    .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())
+                                    .and_then(|el| el.value().parse().ok())
+                                    .map(Msg::UpdateFocusBeforeLongBreak)
+                            })}
+                            value={ self.cycle_config.focus_before_long_break.to_string() }/>
+                    </label>
+                </div>
                 <div class="grid periods">
                     <div>
                     { for self.periods.iter().enumerate().map(|(i, period)| {
@@ -345,7 +1006,17 @@ impl Component for App {
                                         })}
                                         value={ period.name.clone() }/>
                                 </div>
-                                <div class="grid">
+                                <div>
+                                    <input type="text" class="duration-input"
+                                        placeholder="e.g. 25m, 1h30m"
+                                        oninput={ctx.link().batch_callback(move |e: InputEvent| {
+                                            e.target()
+                                             .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())
+                                             .map(|el| Msg::UpdateDuration(i, el.value()))
+                                        })}
+                                        value={ format_duration_human(period.duration) }/>
+                                </div>
+                                <div class="grid numeric-duration">
                                     <input type="number" min=0
                                     oninput={ctx.link().batch_callback(move |e: InputEvent| {
                                         e.target()
@@ -0,0 +1,30 @@
+use gloo::console;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{BroadcastChannel, MessageEvent};
+
+/// A thin wrapper around `BroadcastChannel` for shuttling JSON payloads
+/// between same-origin tabs. Keeps the `onmessage` closure alive for as
+/// long as the channel itself is kept alive.
+pub struct Channel {
+    channel: BroadcastChannel,
+    _onmessage: Closure<dyn FnMut(MessageEvent)>,
+}
+
+impl Channel {
+    pub fn new(name: &str, mut on_message: impl FnMut(String) + 'static) -> Result<Self, JsValue> {
+        let channel = BroadcastChannel::new(name)?;
+        let onmessage = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+            if let Some(text) = event.data().as_string() {
+                on_message(text);
+            }
+        });
+        channel.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+        Ok(Self { channel, _onmessage: onmessage })
+    }
+
+    pub fn send(&self, payload: &str) {
+        if let Err(e) = self.channel.post_message(&JsValue::from_str(payload)) {
+            console::error!("Could not post sync message", e);
+        }
+    }
+}